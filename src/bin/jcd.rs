@@ -1,30 +1,87 @@
-use std::collections::{BTreeMap, HashMap};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
-use std::rc::Rc;
 use std::string::String;
+use std::sync::Arc;
 
 use clap::Parser;
+use glob::Pattern;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
 use zip::read::ZipFile;
 use zip::ZipArchive;
 
+const ARCHIVE_EXTENSIONS: [&str; 3] = ["jar", "war", "ear"];
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     #[arg(
         short,
         long = "jars",
-        required = true,
-        help = "The jar list joined by semicolon"
+        help = "The jar list joined by semicolon. Either --jars or --dir is required"
+    )]
+    jar_list: Option<String>,
+
+    #[arg(
+        long = "dir",
+        action = clap::ArgAction::Append,
+        help = "A directory to recursively scan for *.jar/*.war/*.ear files, can be declared multiple times"
     )]
-    jar_list: String,
+    dirs: Vec<String>,
 
     #[arg(short, long)]
     #[clap(value_enum, default_value_t = DistinctFrom::Size)]
     check: DistinctFrom,
 
-    #[arg(short, long, action = clap::ArgAction::Append, help = "The exclude package prefix, can be declared multiple times")]
+    #[arg(
+        short,
+        long,
+        action = clap::ArgAction::Append,
+        help = "A glob to exclude class entries, layered like a .gitignore: later patterns override earlier ones and a leading `!` re-includes, can be declared multiple times"
+    )]
     exclude: Vec<String>,
+
+    #[arg(
+        short,
+        long = "include",
+        action = clap::ArgAction::Append,
+        help = "A glob to re-include class entries, applied after every --exclude, can be declared multiple times"
+    )]
+    include: Vec<String>,
+
+    #[arg(
+        long = "classpath-order",
+        action = clap::ArgAction::SetTrue,
+        help = "For each conflicting class, also resolve which jar wins on a JVM classpath (the earliest jar in --jars order) and which jars it shadows"
+    )]
+    classpath_order: bool,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+    format: OutputFormat,
+
+    #[arg(
+        long = "fail-on-conflict",
+        action = clap::ArgAction::SetTrue,
+        help = "Exit with a non-zero status code if any conflict is found, so the detector can gate a CI build"
+    )]
+    fail_on_conflict: bool,
+
+    #[arg(
+        long = "max-depth",
+        default_value_t = 5,
+        help = "Maximum recursion depth when descending into nested/fat jars (BOOT-INF/lib, WEB-INF/lib, shaded jars)"
+    )]
+    max_depth: u32,
+
+    #[arg(
+        long = "jobs",
+        help = "Number of worker threads to scan jars with (defaults to rayon's global pool, usually one per core)"
+    )]
+    jobs: Option<usize>,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
@@ -32,6 +89,39 @@ enum DistinctFrom {
     Size,
     Crc,
     None,
+    Version,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Serialize)]
+struct ConflictGroup {
+    distinct_key: u64,
+    jars: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ClassConflict {
+    class: String,
+    groups: Vec<ConflictGroup>,
+}
+
+#[derive(Serialize)]
+struct ArtifactVersions {
+    artifact: String,
+    versions: Vec<String>,
+    newest: String,
+}
+
+#[derive(Serialize)]
+struct ClasspathWinner {
+    class: String,
+    winner: String,
+    shadowed: Vec<String>,
 }
 
 const DISTINCT_FROM_NONE: u64 = 0;
@@ -39,7 +129,23 @@ const DISTINCT_FROM_NONE: u64 = 0;
 fn main() {
     let args = Args::parse();
 
-    let paths: Vec<_> = args.jar_list.split(';').collect();
+    let mut paths: Vec<String> = match &args.jar_list {
+        Some(list) => list.split(';').map(|s| s.to_string()).collect(),
+        None => Vec::new(),
+    };
+    // --jars keeps the user's declared order (it doubles as classpath order),
+    // but a --dir scan's order is filesystem-dependent, so sort its jars
+    // before appending them to keep the overall result deterministic.
+    let mut discovered: Vec<String> = Vec::new();
+    for dir in &args.dirs {
+        collect_archives(Path::new(dir), &mut discovered);
+    }
+    discovered.sort();
+    paths.extend(discovered);
+    if paths.is_empty() {
+        eprintln!("Must provide at least one jar via --jars or a directory via --dir");
+        std::process::exit(2);
+    }
     if paths.len() < 2 {
         println!(
             "Only have {:?} jar file. No conflict class detected.",
@@ -48,16 +154,19 @@ fn main() {
         return;
     }
 
-    // <class, <crc32, jar-list>>
-    let mut name_to_sources: BTreeMap<Rc<String>, HashMap<u64, Vec<Rc<String>>>> = BTreeMap::new();
-
-    // build all class to jar mapping
-    for x in paths {
-        let jar_name = Rc::new(get_jar_name(x));
-        extract_class_filenames_from_jar(x, &mut name_to_sources, jar_name, &args);
+    if args.check == DistinctFrom::Version {
+        run_version_report(&paths, &args);
+        return;
     }
 
-    let result: BTreeMap<Rc<String>, HashMap<u64, Vec<Rc<String>>>> = name_to_sources
+    // the order jars are declared in --jars is the classpath order: the JVM
+    // resolves a class to the first jar on the path that provides it.
+    let classpath_order: Vec<String> = paths.iter().map(|p| get_jar_name(p)).collect();
+
+    // build all class to jar mapping, one jar per rayon worker
+    let name_to_sources = build_name_to_sources(&paths, &args);
+
+    let result: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> = name_to_sources
         .into_iter()
         .filter(|(_k, v)| match args.check {
             DistinctFrom::None => v.get(&DISTINCT_FROM_NONE).unwrap().len() >= 2,
@@ -65,8 +174,294 @@ fn main() {
         })
         .collect();
 
-    for (name, jar) in result {
-        println!("{:?}, {:?}", name, jar)
+    if args.classpath_order {
+        let report = classpath_order_report(&result, &classpath_order);
+        match args.format {
+            OutputFormat::Text => {
+                for c in &report {
+                    println!("{}: {} -> {:?}", c.class, c.winner, c.shadowed);
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            }
+        }
+    } else {
+        match args.format {
+            OutputFormat::Text => {
+                for (name, jar) in &result {
+                    println!("{:?}, {:?}", name, jar)
+                }
+            }
+            OutputFormat::Json => {
+                let conflicts = to_conflicts(&result);
+                println!("{}", serde_json::to_string_pretty(&conflicts).unwrap());
+            }
+        }
+    }
+
+    if args.fail_on_conflict && !result.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+// Flattens the conflict map into a JSON-friendly shape, with distinct_key
+// groups sorted for stable output.
+fn to_conflicts(result: &BTreeMap<String, HashMap<u64, Vec<Arc<str>>>>) -> Vec<ClassConflict> {
+    result
+        .iter()
+        .map(|(name, groups)| {
+            let mut groups: Vec<ConflictGroup> = groups
+                .iter()
+                .map(|(distinct_key, jars)| ConflictGroup {
+                    distinct_key: *distinct_key,
+                    jars: jars.iter().map(|jar| jar.to_string()).collect(),
+                })
+                .collect();
+            groups.sort_by_key(|g| g.distinct_key);
+            ClassConflict {
+                class: name.clone(),
+                groups,
+            }
+        })
+        .collect()
+}
+
+// For each conflicting class, the first jar on the classpath that provides
+// it wins; every later jar providing the same class name is shadowed.
+fn classpath_order_report(
+    result: &BTreeMap<String, HashMap<u64, Vec<Arc<str>>>>,
+    classpath_order: &[String],
+) -> Vec<ClasspathWinner> {
+    let mut report = Vec::new();
+    for (name, groups) in result {
+        let mut jars: Vec<&Arc<str>> = groups.values().flatten().collect();
+        // `groups` is a HashMap, so its iteration order (and thus the order
+        // `jars` starts in) is randomized per process. Break ties on
+        // classpath position by the label itself so the winner is the same
+        // every run, not whichever tied jar the hasher happened to yield first.
+        jars.sort_by(|a, b| {
+            classpath_position(a, classpath_order)
+                .cmp(&classpath_position(b, classpath_order))
+                .then_with(|| a.as_ref().cmp(b.as_ref()))
+        });
+        jars.dedup_by(|a, b| a.as_ref() == b.as_ref());
+
+        if let Some((winner, shadowed)) = jars.split_first() {
+            if shadowed.is_empty() {
+                continue;
+            }
+            report.push(ClasspathWinner {
+                class: name.clone(),
+                winner: winner.to_string(),
+                shadowed: shadowed.iter().map(|jar| jar.to_string()).collect(),
+            });
+        }
+    }
+    report
+}
+
+// A nested jar's source label carries its full embedding path, e.g.
+// `app.jar!/BOOT-INF/lib/guava.jar`, which never appears in classpath_order
+// itself (that list only has top-level jar names). It loads as part of its
+// top-level jar, so it resolves to that jar's classpath position.
+fn classpath_position(jar: &str, classpath_order: &[String]) -> usize {
+    let top_level = jar.split("!/").next().unwrap_or(jar);
+    classpath_order
+        .iter()
+        .position(|x| x == top_level)
+        .unwrap_or(usize::MAX)
+}
+
+// The classic duplicate-dependency diagnosis: two versions of the *same*
+// artifact on the classpath, which name/size/crc class comparison can't see.
+// Groups jars by artifact base name and reports artifacts with 2+ distinct
+// versions, sorted so that e.g. 1.2.0 < 1.2.10 and 2.0.0-SNAPSHOT < 2.0.0.
+fn run_version_report(paths: &[String], args: &Args) {
+    let filename_version_re =
+        Regex::new(r"-(\d+(?:\.\d+)*(?:[.-][0-9A-Za-z]+)*)\.(?:jar|war|ear)$").unwrap();
+
+    let mut artifacts: BTreeMap<String, Vec<(SemVer, String, String)>> = BTreeMap::new();
+    for path in paths {
+        let jar_name = get_jar_name(path);
+        let (regex_base, filename_version) = split_filename_version(&jar_name, &filename_version_re);
+        let version = read_manifest_version(path).or(filename_version);
+        // Derive the artifact name the same way regardless of which source
+        // the version came from, so e.g. `guava.jar` (version from its
+        // manifest) and `guava-30.0.jar` (version from its filename) group
+        // under the same artifact key.
+        let artifact = regex_base.unwrap_or_else(|| strip_archive_extension(&jar_name));
+
+        if let Some(version) = version {
+            let semver = SemVer::parse(&version);
+            artifacts
+                .entry(artifact)
+                .or_default()
+                .push((semver, version, jar_name));
+        }
+    }
+
+    let mut conflicts: Vec<ArtifactVersions> = Vec::new();
+    for (artifact, mut versions) in artifacts {
+        let distinct: HashSet<&str> = versions.iter().map(|(_, v, _)| v.as_str()).collect();
+        if distinct.len() < 2 {
+            continue;
+        }
+        versions.sort_by(|a, b| a.0.cmp(&b.0));
+        let newest = versions.last().unwrap().1.clone();
+        conflicts.push(ArtifactVersions {
+            artifact,
+            versions: versions.into_iter().map(|(_, v, _)| v).collect(),
+            newest,
+        });
+    }
+
+    match args.format {
+        OutputFormat::Text => {
+            for c in &conflicts {
+                println!("{}: {:?} (newest: {})", c.artifact, c.versions, c.newest);
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&conflicts).unwrap());
+        }
+    }
+
+    if args.fail_on_conflict && !conflicts.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+// Reads `META-INF/MANIFEST.MF` looking for the standard version headers, in
+// the order a JVM tool would trust them. An unreadable jar path panics, the
+// same as every other path in this file; only a missing/unversioned
+// manifest entry is a normal `None`.
+fn read_manifest_version(path: &str) -> Option<String> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) => panic!("path: {} err: {}", path, e),
+    };
+    let mut zip = ZipArchive::new(file).unwrap();
+    let mut manifest = String::new();
+    zip.by_name("META-INF/MANIFEST.MF")
+        .ok()?
+        .read_to_string(&mut manifest)
+        .ok()?;
+
+    for header in ["Implementation-Version", "Bundle-Version", "Specification-Version"] {
+        let prefix = format!("{}:", header);
+        for line in manifest.lines() {
+            if let Some(value) = line.strip_prefix(&prefix) {
+                let value = value.trim();
+                if !value.is_empty() {
+                    return Some(value.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+// Splits a jar filename into its artifact base name and version, e.g.
+// `guava-31.1-jre.jar` -> (`guava`, `31.1-jre`). Returns `(None, None)` when
+// the filename carries no recognizable version segment.
+fn split_filename_version(jar_name: &str, re: &Regex) -> (Option<String>, Option<String>) {
+    match re.captures(jar_name) {
+        Some(caps) => {
+            let whole = caps.get(0).unwrap();
+            let base = jar_name[..whole.start()].to_string();
+            let version = caps.get(1).unwrap().as_str().to_string();
+            (Some(base), Some(version))
+        }
+        None => (None, None),
+    }
+}
+
+// Strips a trailing .jar/.war/.ear extension, used as the artifact base name
+// when the filename carries no recognizable version segment for
+// split_filename_version to split off.
+fn strip_archive_extension(name: &str) -> String {
+    match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ARCHIVE_EXTENSIONS.contains(&ext) => {
+            name[..name.len() - ext.len() - 1].to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+// A pragmatic semver: numeric dot-separated components compared
+// lexicographically, then a qualifier (pre-release/build tag) where its
+// absence outranks its presence, e.g. `2.0.0` > `2.0.0-SNAPSHOT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SemVer {
+    numeric: Vec<u64>,
+    qualifier: Option<String>,
+}
+
+impl SemVer {
+    fn parse(raw: &str) -> SemVer {
+        let mut numeric = Vec::new();
+        let mut rest = raw;
+        loop {
+            let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            if digits_end == 0 {
+                break;
+            }
+            numeric.push(rest[..digits_end].parse::<u64>().unwrap_or(0));
+            rest = &rest[digits_end..];
+            match rest.strip_prefix('.') {
+                Some(stripped) => rest = stripped,
+                None => break,
+            }
+        }
+
+        let qualifier = match rest.strip_prefix('-').or_else(|| rest.strip_prefix('.')) {
+            Some(q) if !q.is_empty() => Some(q.to_string()),
+            _ => None,
+        };
+        SemVer { numeric, qualifier }
+    }
+}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.numeric.cmp(&other.numeric) {
+            Ordering::Equal => match (&self.qualifier, &other.qualifier) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            },
+            other => other,
+        }
+    }
+}
+
+// Walks a directory tree, collecting every *.jar/*.war/*.ear it finds so
+// users can point the tool at a lib/ directory instead of enumerating jars.
+fn collect_archives(dir: &Path, out: &mut Vec<String>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => panic!("dir: {:?} err: {}", dir, e),
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_archives(&path, out);
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ARCHIVE_EXTENSIONS.contains(&ext))
+            .unwrap_or(false)
+        {
+            out.push(path.to_string_lossy().into_owned());
+        }
     }
 }
 
@@ -78,26 +473,93 @@ fn get_jar_name(path: &str) -> String {
     }
 }
 
+// Scans every top-level jar independently (on rayon's global thread pool, or
+// on a dedicated pool of --jobs threads when given) into its own local map, then folds the
+// per-jar maps together. Each worker only ever touches its own local map, so
+// there is no shared `&mut` state to synchronize while scanning.
+fn build_name_to_sources(
+    paths: &[String],
+    args: &Args,
+) -> BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> {
+    let scan = || {
+        paths
+            .par_iter()
+            .map(|path| extract_class_filenames_from_jar(path, args))
+            .reduce(BTreeMap::new, |mut a, b| {
+                merge_into(&mut a, b);
+                a
+            })
+    };
+
+    match args.jobs {
+        Some(jobs) => rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build()
+            .unwrap()
+            .install(scan),
+        None => scan(),
+    }
+}
+
+fn merge_into(
+    target: &mut BTreeMap<String, HashMap<u64, Vec<Arc<str>>>>,
+    source: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>>,
+) {
+    for (name, groups) in source {
+        let entry = target.entry(name).or_default();
+        for (distinct_from, mut jars) in groups {
+            entry.entry(distinct_from).or_default().append(&mut jars);
+        }
+    }
+}
+
 fn extract_class_filenames_from_jar(
     path: &str,
-    name_to_sources: &mut BTreeMap<Rc<String>, HashMap<u64, Vec<Rc<String>>>>,
-    jar_name: Rc<String>,
     args: &Args,
-) {
+) -> BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> {
     let jar = match File::open(path) {
         Ok(f) => f,
         Err(e) => {
             panic!("path: {} err: {}", path, e);
         }
     };
-    let mut zip = ZipArchive::new(jar).unwrap();
+    let zip = ZipArchive::new(jar).unwrap();
+    let mut local = BTreeMap::new();
+    extract_class_filenames(zip, &mut local, get_jar_name(path), 0, args);
+    local
+}
+
+// Recurses into fat/shaded jars (Spring Boot's BOOT-INF/lib, a war's
+// WEB-INF/lib, shaded dependency jars) by buffering each nested archive
+// entry into a `Cursor<Vec<u8>>`, since `ZipArchive` needs `Read + Seek` and
+// a zip entry by itself is only `Read`. `source_label` accumulates the
+// nesting path, e.g. `app.jar!/BOOT-INF/lib/guava.jar`, so conflicts inside
+// a fat jar are attributed to the exact embedded archive.
+fn extract_class_filenames<R: Read + Seek>(
+    mut zip: ZipArchive<R>,
+    name_to_sources: &mut BTreeMap<String, HashMap<u64, Vec<Arc<str>>>>,
+    source_label: String,
+    depth: u32,
+    args: &Args,
+) {
+    let jar_name: Arc<str> = Arc::from(source_label.as_str());
+    let mut nested_archives: Vec<(String, Cursor<Vec<u8>>)> = Vec::new();
 
     for i in 0..zip.len() {
-        let zip_entry = zip.by_index(i).unwrap();
-        let name = zip_entry.name();
-        if filter(name, &args.exclude) {
+        let mut zip_entry = zip.by_index(i).unwrap();
+        let name = zip_entry.name().to_string();
+
+        if depth < args.max_depth && is_archive_name(&name) {
+            let mut buf = Vec::new();
+            if zip_entry.read_to_end(&mut buf).is_ok() {
+                nested_archives.push((format!("{}!/{}", source_label, name), Cursor::new(buf)));
+            }
+            continue;
+        }
+
+        if filter(&name, &args.exclude, &args.include) {
             let distinct_from = get_distinct_from(&zip_entry, args);
-            match name_to_sources.get_mut(&name.to_string()) {
+            match name_to_sources.get_mut(&name) {
                 Some(entries) => match entries.get_mut(&distinct_from) {
                     Some(v) => {
                         v.push(jar_name.clone());
@@ -111,11 +573,25 @@ fn extract_class_filenames_from_jar(
                     let v = vec![jar_name.clone()];
                     let mut entry = HashMap::new();
                     entry.insert(distinct_from, v);
-                    name_to_sources.insert(Rc::new(name.to_string()), entry);
+                    name_to_sources.insert(name, entry);
                 }
             }
         }
     }
+
+    for (label, cursor) in nested_archives {
+        if let Ok(nested_zip) = ZipArchive::new(cursor) {
+            extract_class_filenames(nested_zip, name_to_sources, label, depth + 1, args);
+        }
+    }
+}
+
+fn is_archive_name(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ARCHIVE_EXTENSIONS.contains(&ext))
+        .unwrap_or(false)
 }
 
 fn get_distinct_from(zip: &ZipFile, arg: &Args) -> u64 {
@@ -123,10 +599,16 @@ fn get_distinct_from(zip: &ZipFile, arg: &Args) -> u64 {
         DistinctFrom::Crc => zip.crc32() as u64,
         DistinctFrom::Size => zip.size(),
         DistinctFrom::None => DISTINCT_FROM_NONE,
+        // `Version` is handled by run_version_report before any jar is
+        // walked class-by-class; it never reaches per-entry comparison.
+        DistinctFrom::Version => DISTINCT_FROM_NONE,
     }
 }
 
-fn filter(name: &str, excludes: &Vec<String>) -> bool {
+// Applies --exclude/--include globs with the same layered override semantics
+// as the `ignore` crate: patterns are applied in declaration order, a leading
+// `!` re-includes, and every --include is applied last so it always wins.
+fn filter(name: &str, excludes: &[String], includes: &[String]) -> bool {
     if !name.ends_with(".class") {
         return false;
     }
@@ -134,12 +616,29 @@ fn filter(name: &str, excludes: &Vec<String>) -> bool {
         return false;
     }
 
+    let mut included = true;
     for exclude in excludes {
-        if name.starts_with(exclude) {
-            return false;
+        let (negate, pattern) = match exclude.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, exclude.as_str()),
+        };
+        if glob_matches(pattern, name) {
+            included = negate;
         }
     }
-    true
+    for include in includes {
+        if glob_matches(include, name) {
+            included = true;
+        }
+    }
+    included
+}
+
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    match Pattern::new(pattern) {
+        Ok(pattern) => pattern.matches(name),
+        Err(e) => panic!("invalid glob {:?}: {}", pattern, e),
+    }
 }
 
 #[test]
@@ -151,8 +650,29 @@ fn test_parse() {
     assert_eq!(args.check, DistinctFrom::Size);
     assert!(args.exclude.is_empty());
 
+    // jar_list is no longer required at the parser level: a jar list can
+    // instead come from one or more --dir entries, checked in main at runtime.
     let args = Args::try_parse_from([""]);
-    assert!(args.is_err());
+    assert!(args.is_ok());
+    let args = args.unwrap();
+    assert!(args.jar_list.is_none());
+    assert!(args.dirs.is_empty());
+    assert!(args.include.is_empty());
+
+    let args = Args::parse_from(["", "--dir", "lib", "--dir", "target/deps"]);
+    assert_eq!(args.dirs, vec!["lib", "target/deps"]);
+
+    let args = Args::parse_from([
+        "",
+        "--jars",
+        "a.jar;b.jar",
+        "--exclude",
+        "com/example/**",
+        "--include",
+        "!com/example/keep/*.class",
+    ]);
+    assert_eq!(args.exclude, vec!["com/example/**"]);
+    assert_eq!(args.include, vec!["!com/example/keep/*.class"]);
 
     let args = Args::parse_from(["", "--jars", "a.jar", "-c", "crc"]);
     assert_eq!(args.check, DistinctFrom::Crc);
@@ -160,6 +680,229 @@ fn test_parse() {
     let args = Args::parse_from(["", "--jars", "a.jar", "-c", "none"]);
     assert_eq!(args.check, DistinctFrom::None);
 
+    let args = Args::parse_from(["", "--jars", "a.jar", "-c", "version"]);
+    assert_eq!(args.check, DistinctFrom::Version);
+
     let args = Args::try_parse_from(["", "--jars", "a.jar", "-c", "none1"]);
     assert!(args.is_err());
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar"]);
+    assert!(!args.classpath_order);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar", "--classpath-order"]);
+    assert!(args.classpath_order);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar"]);
+    assert_eq!(args.format, OutputFormat::Text);
+    assert!(!args.fail_on_conflict);
+
+    let args = Args::parse_from([
+        "",
+        "--jars",
+        "a.jar;b.jar",
+        "--format",
+        "json",
+        "--fail-on-conflict",
+    ]);
+    assert_eq!(args.format, OutputFormat::Json);
+    assert!(args.fail_on_conflict);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar"]);
+    assert_eq!(args.max_depth, 5);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar", "--max-depth", "1"]);
+    assert_eq!(args.max_depth, 1);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar"]);
+    assert_eq!(args.jobs, None);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar", "--jobs", "4"]);
+    assert_eq!(args.jobs, Some(4));
+}
+
+#[test]
+fn test_is_archive_name() {
+    assert!(is_archive_name("BOOT-INF/lib/guava.jar"));
+    assert!(is_archive_name("WEB-INF/lib/commons.war"));
+    assert!(!is_archive_name("com/example/Foo.class"));
+}
+
+#[test]
+fn test_merge_into_combines_per_jar_maps() {
+    let mut a: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> = BTreeMap::new();
+    a.entry("com/acme/Foo.class".to_string())
+        .or_default()
+        .insert(1, vec![Arc::from("a.jar")]);
+
+    let mut b: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> = BTreeMap::new();
+    b.entry("com/acme/Foo.class".to_string())
+        .or_default()
+        .insert(1, vec![Arc::from("b.jar")]);
+    b.entry("com/acme/Bar.class".to_string())
+        .or_default()
+        .insert(2, vec![Arc::from("b.jar")]);
+
+    merge_into(&mut a, b);
+
+    assert_eq!(a.len(), 2);
+    assert_eq!(
+        a["com/acme/Foo.class"][&1]
+            .iter()
+            .map(|jar| jar.as_ref())
+            .collect::<Vec<&str>>(),
+        vec!["a.jar", "b.jar"]
+    );
+}
+
+#[test]
+fn test_filter_globs() {
+    let excludes = vec!["com/example/**".to_string()];
+    let includes: Vec<String> = vec![];
+    assert!(!filter("com/example/Foo.class", &excludes, &includes));
+    assert!(filter("com/other/Foo.class", &excludes, &includes));
+
+    // a leading `!` in an --exclude entry re-includes, like a .gitignore.
+    let layered = vec![
+        "com/example/**".to_string(),
+        "!com/example/keep/*.class".to_string(),
+    ];
+    assert!(!filter("com/example/Foo.class", &layered, &includes));
+    assert!(filter("com/example/keep/Foo.class", &layered, &includes));
+
+    // an explicit --include always wins, applied after every --exclude.
+    let includes = vec!["com/example/Foo.class".to_string()];
+    assert!(filter("com/example/Foo.class", &excludes, &includes));
+}
+
+#[test]
+fn test_semver_ordering() {
+    assert!(SemVer::parse("1.2.0") < SemVer::parse("1.2.10"));
+    assert!(SemVer::parse("2.0.0-SNAPSHOT") < SemVer::parse("2.0.0"));
+    assert_eq!(SemVer::parse("1.0"), SemVer::parse("1.0"));
+}
+
+#[cfg(test)]
+fn build_test_jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default();
+    for (name, data) in entries {
+        writer.start_file(*name, options).unwrap();
+        std::io::Write::write_all(&mut writer, data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_extract_class_filenames_recurses_into_nested_jar() {
+    let inner = build_test_jar(&[("com/acme/Foo.class", b"inner-bytes")]);
+    let outer = build_test_jar(&[("BOOT-INF/lib/guava.jar", &inner)]);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar"]);
+    let mut map: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> = BTreeMap::new();
+    let zip = ZipArchive::new(Cursor::new(outer)).unwrap();
+    extract_class_filenames(zip, &mut map, "app.jar".to_string(), 0, &args);
+
+    let sources: Vec<&str> = map["com/acme/Foo.class"]
+        .values()
+        .flatten()
+        .map(|jar| jar.as_ref())
+        .collect();
+    assert_eq!(sources, vec!["app.jar!/BOOT-INF/lib/guava.jar"]);
+}
+
+#[test]
+fn test_extract_class_filenames_respects_max_depth() {
+    let inner = build_test_jar(&[("com/acme/Foo.class", b"inner-bytes")]);
+    let outer = build_test_jar(&[("BOOT-INF/lib/guava.jar", &inner)]);
+
+    let args = Args::parse_from(["", "--jars", "a.jar;b.jar", "--max-depth", "0"]);
+    let mut map: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> = BTreeMap::new();
+    let zip = ZipArchive::new(Cursor::new(outer)).unwrap();
+    extract_class_filenames(zip, &mut map, "app.jar".to_string(), 0, &args);
+
+    // at max-depth 0 the nested jar is never descended into, so its class
+    // never makes it into the map.
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_build_name_to_sources_merges_parallel_jar_scans() {
+    let dir = std::env::temp_dir().join(format!("jcd-test-fixture-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let jar_a = dir.join("a.jar");
+    let jar_b = dir.join("b.jar");
+    std::fs::write(&jar_a, build_test_jar(&[("com/acme/Foo.class", b"AAAA")])).unwrap();
+    std::fs::write(&jar_b, build_test_jar(&[("com/acme/Foo.class", b"BB")])).unwrap();
+
+    let paths = vec![jar_a.to_string_lossy().into_owned(), jar_b.to_string_lossy().into_owned()];
+    let args = Args::parse_from(["", "--jars", &paths.join(";"), "--jobs", "2"]);
+    let merged = build_name_to_sources(&paths, &args);
+
+    // different contents means different sizes, so they land in distinct
+    // distinct_key groups instead of being merged together.
+    let groups = &merged["com/acme/Foo.class"];
+    assert_eq!(groups.len(), 2);
+    let mut all_jars: Vec<&str> = groups.values().flatten().map(|jar| jar.as_ref()).collect();
+    all_jars.sort();
+    assert_eq!(all_jars, vec!["a.jar", "b.jar"]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_classpath_order_report_breaks_ties_deterministically() {
+    // Two nested jars from the same top-level jar tie on classpath position;
+    // the HashMap they come from iterates in randomized order, so without a
+    // tie-break the winner would flip-flop between runs.
+    let mut groups: HashMap<u64, Vec<Arc<str>>> = HashMap::new();
+    groups.insert(1, vec![Arc::from("app.jar!/BOOT-INF/lib/lib1.jar")]);
+    groups.insert(2, vec![Arc::from("app.jar!/BOOT-INF/lib/lib2.jar")]);
+    let mut result: BTreeMap<String, HashMap<u64, Vec<Arc<str>>>> = BTreeMap::new();
+    result.insert("com/acme/Foo.class".to_string(), groups);
+
+    let classpath_order = vec!["app.jar".to_string()];
+
+    for _ in 0..20 {
+        let report = classpath_order_report(&result, &classpath_order);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].winner, "app.jar!/BOOT-INF/lib/lib1.jar");
+        assert_eq!(
+            report[0].shadowed,
+            vec!["app.jar!/BOOT-INF/lib/lib2.jar".to_string()]
+        );
+    }
+}
+
+#[test]
+fn test_read_manifest_version_reads_implementation_version() {
+    let manifest = b"Manifest-Version: 1.0\nImplementation-Version: 31.1\n";
+    let jar = build_test_jar(&[("META-INF/MANIFEST.MF", manifest)]);
+    let dir = std::env::temp_dir().join(format!("jcd-test-manifest-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("guava.jar");
+    std::fs::write(&path, jar).unwrap();
+
+    let version = read_manifest_version(path.to_str().unwrap());
+
+    std::fs::remove_dir_all(&dir).ok();
+    assert_eq!(version.as_deref(), Some("31.1"));
+}
+
+#[test]
+#[should_panic]
+fn test_read_manifest_version_panics_on_unreadable_path() {
+    read_manifest_version("/nonexistent/jcd-test-path/missing.jar");
+}
+
+#[test]
+fn test_split_filename_version() {
+    let re = Regex::new(r"-(\d+(?:\.\d+)*(?:[.-][0-9A-Za-z]+)*)\.(?:jar|war|ear)$").unwrap();
+
+    let (base, version) = split_filename_version("guava-31.1-jre.jar", &re);
+    assert_eq!(base.as_deref(), Some("guava"));
+    assert_eq!(version.as_deref(), Some("31.1-jre"));
+
+    let (base, version) = split_filename_version("app.jar", &re);
+    assert_eq!(base, None);
+    assert_eq!(version, None);
 }